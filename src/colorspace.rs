@@ -0,0 +1,195 @@
+//! RGB <-> HSV colorspace conversion, used both for whole-image conversion
+//! (`convert`) and single-channel extraction (`extract_channel`).
+
+use image::{DynamicImage, GrayImage, Luma, Pixel, Rgb, RgbImage};
+
+/// Converts an RGB triple to HSV, returning hue in degrees (0-360) and
+/// saturation/value normalized to 0-1.
+pub fn rgb_to_hsb(rgb: Rgb<u8>) -> (f32, f32, f32) {
+    let r = rgb[0] as f32 / 255.0;
+    let g = rgb[1] as f32 / 255.0;
+    let b = rgb[2] as f32 / 255.0;
+
+    let max = r.max(g.max(b));
+    let min = r.min(g.min(b));
+    let delta = max - min;
+
+    let hue = if delta == 0.0 {
+        0.0
+    } else if max == r {
+        // `%` is a remainder (sign of the dividend), not a true modulo, so
+        // when blue > green this would otherwise produce a negative hue
+        // instead of wrapping into the 300-360 degree range.
+        60.0 * (((g - b) / delta).rem_euclid(6.0))
+    } else if max == g {
+        60.0 * (((b - r) / delta) + 2.0)
+    } else {
+        60.0 * (((r - g) / delta) + 4.0)
+    };
+
+    let saturation = if max == 0.0 { 0.0 } else { delta / max };
+
+    (hue, saturation, max)
+}
+
+/// Converts an HSV triple (hue in degrees 0-360, saturation/value 0-1) back
+/// to an RGB triple.
+pub fn hsb_to_rgb(hsb: (f32, f32, f32)) -> Rgb<u8> {
+    let (hue, saturation, value) = hsb;
+
+    let c = value * saturation;
+    let h_prime = (hue.rem_euclid(360.0)) / 60.0;
+    let x = c * (1.0 - (h_prime % 2.0 - 1.0).abs());
+    let m = value - c;
+
+    let (r, g, b) = if h_prime < 1.0 {
+        (c, x, 0.0)
+    } else if h_prime < 2.0 {
+        (x, c, 0.0)
+    } else if h_prime < 3.0 {
+        (0.0, c, x)
+    } else if h_prime < 4.0 {
+        (0.0, x, c)
+    } else if h_prime < 5.0 {
+        (x, 0.0, c)
+    } else {
+        (c, 0.0, x)
+    };
+
+    Rgb([
+        ((r + m) * 255.0).round() as u8,
+        ((g + m) * 255.0).round() as u8,
+        ((b + m) * 255.0).round() as u8,
+    ])
+}
+
+/// Converts a whole image to the named target colorspace, returned as a
+/// `DynamicImage` ready for re-encoding. `hsv` packs hue/saturation/value
+/// into the R/G/B channels (hue scaled from 0-360 to 0-255) rather than
+/// changing the pixel format, since most image formats have no native
+/// HSV representation. `from_hsv` reverses that packing back to plain RGB,
+/// for round-tripping an image a caller previously converted to `hsv`.
+pub fn convert(img: &DynamicImage, target: &str) -> DynamicImage {
+    match target {
+        "grayscale" => DynamicImage::ImageLuma8(img.to_luma8()),
+        "hsv" => DynamicImage::ImageRgb8(map_rgb(img, |rgb| {
+            let (hue, saturation, value) = rgb_to_hsb(rgb);
+            Rgb([
+                (hue / 360.0 * 255.0).round() as u8,
+                (saturation * 255.0).round() as u8,
+                (value * 255.0).round() as u8,
+            ])
+        })),
+        "from_hsv" => DynamicImage::ImageRgb8(map_rgb(img, |packed| {
+            hsb_to_rgb((
+                packed[0] as f32 / 255.0 * 360.0,
+                packed[1] as f32 / 255.0,
+                packed[2] as f32 / 255.0,
+            ))
+        })),
+        _ => DynamicImage::ImageRgb8(img.to_rgb8()), // "rgb" and anything unrecognized
+    }
+}
+
+/// Extracts a single channel (`r`, `g`, `b`, `h`, `s`, `v`, or `luma`) as an
+/// 8-bit grayscale image.
+pub fn extract_channel(img: &DynamicImage, which: &str) -> DynamicImage {
+    let rgb = img.to_rgb8();
+    let mut out = GrayImage::new(rgb.width(), rgb.height());
+
+    for (x, y, pixel) in rgb.enumerate_pixels() {
+        let value = match which {
+            "r" => pixel[0],
+            "g" => pixel[1],
+            "b" => pixel[2],
+            "h" | "s" | "v" => {
+                let (hue, saturation, value) = rgb_to_hsb(*pixel);
+                match which {
+                    "h" => (hue / 360.0 * 255.0).round() as u8,
+                    "s" => (saturation * 255.0).round() as u8,
+                    _ => (value * 255.0).round() as u8,
+                }
+            }
+            _ => pixel.to_luma()[0], // "luma" and anything unrecognized
+        };
+        out.put_pixel(x, y, Luma([value]));
+    }
+
+    DynamicImage::ImageLuma8(out)
+}
+
+/// Applies `f` to every pixel of an image's RGB view and collects the result
+/// into a new RGB image of the same dimensions.
+fn map_rgb(img: &DynamicImage, f: impl Fn(Rgb<u8>) -> Rgb<u8>) -> RgbImage {
+    let rgb = img.to_rgb8();
+    let mut out = RgbImage::new(rgb.width(), rgb.height());
+
+    for (x, y, pixel) in rgb.enumerate_pixels() {
+        out.put_pixel(x, y, f(*pixel));
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rgb_to_hsb_wraps_hue_into_positive_range_for_crimson_pink() {
+        // Red is the max channel and blue > green: the naive `%` remainder
+        // would yield a negative hue instead of wrapping into 300-360.
+        let (hue, _, _) = rgb_to_hsb(Rgb([255, 0, 128]));
+
+        assert!(hue >= 0.0 && hue <= 360.0, "hue {hue} is out of range");
+        assert!((hue - 329.88).abs() < 0.5, "hue {hue} is not close to the true ~329.9");
+    }
+
+    #[test]
+    fn rgb_to_hsb_hsb_to_rgb_round_trip() {
+        let original = Rgb([200, 40, 100]);
+
+        let hsb = rgb_to_hsb(original);
+        let round_tripped = hsb_to_rgb(hsb);
+
+        for c in 0..3 {
+            assert!(
+                (original[c] as i16 - round_tripped[c] as i16).abs() <= 1,
+                "channel {c}: {:?} vs {:?}",
+                original,
+                round_tripped
+            );
+        }
+    }
+
+    #[test]
+    fn convert_hsv_then_from_hsv_round_trips_close_to_original() {
+        let mut img = RgbImage::new(1, 1);
+        img.put_pixel(0, 0, Rgb([255, 0, 128]));
+        let original = DynamicImage::ImageRgb8(img);
+
+        let hsv = convert(&original, "hsv");
+        let back = convert(&hsv, "from_hsv");
+
+        let back_rgb = back.to_rgb8();
+        let pixel = back_rgb.get_pixel(0, 0);
+        for c in 0..3 {
+            assert!(
+                (pixel[c] as i16 - [255, 0, 128][c] as i16).abs() <= 2,
+                "channel {c}: got {:?}",
+                pixel
+            );
+        }
+    }
+
+    #[test]
+    fn extract_channel_reads_individual_rgb_channels() {
+        let mut img = RgbImage::new(1, 1);
+        img.put_pixel(0, 0, Rgb([10, 20, 30]));
+        let dynamic = DynamicImage::ImageRgb8(img);
+
+        assert_eq!(extract_channel(&dynamic, "r").to_luma8().get_pixel(0, 0)[0], 10);
+        assert_eq!(extract_channel(&dynamic, "g").to_luma8().get_pixel(0, 0)[0], 20);
+        assert_eq!(extract_channel(&dynamic, "b").to_luma8().get_pixel(0, 0)[0], 30);
+    }
+}
@@ -1,3 +1,6 @@
+mod colorspace;
+mod metadata;
+
 use exif::{In, Reader, Tag};
 use image::{imageops::FilterType, DynamicImage, GenericImageView, ImageFormat, Pixel, Rgb};
 use std::{collections::HashMap, fmt::Write, io::Cursor};
@@ -9,6 +12,24 @@ use serde::Serialize;
 struct ImageAnalysis {
     exif_data: Vec<[String; 2]>,
     unique_colors: Vec<String>,
+    metadata: ImageMetadata,
+}
+
+/// Default number of swatches used for the `unique_colors` field of `ImageAnalysis`.
+const DEFAULT_PALETTE_SIZE: usize = 16;
+
+/// Commonly-needed EXIF fields surfaced in machine-readable form, so JS
+/// consumers don't have to re-parse the display strings in `exif_data`.
+#[derive(Serialize, Default)]
+struct ImageMetadata {
+    capture_date: Option<String>,
+    camera_make: Option<String>,
+    camera_model: Option<String>,
+    exposure_time: Option<f64>,
+    iso: Option<f64>,
+    f_number: Option<f64>,
+    gps_latitude: Option<f64>,
+    gps_longitude: Option<f64>,
 }
 
 /// Reads the orientation from the image EXIF data.
@@ -65,80 +86,176 @@ fn apply_orientation(mut img: DynamicImage, orientation: u16) -> DynamicImage {
     }
 }
 
-// A simple function to convert RGB to HSB
-fn rgb_to_hsb(rgb: Rgb<u8>) -> (f32, f32, f32) {
-    let r = rgb[0] as f32 / 255.0;
-    let g = rgb[1] as f32 / 255.0;
-    let b = rgb[2] as f32 / 255.0;
-
-    let max = r.max(g.max(b));
-    let min = r.min(g.min(b));
-    let delta = max - min;
-
-    let hue = if delta == 0.0 {
-        0.0
-    } else if max == r {
-        60.0 * (((g - b) / delta) % 6.0)
-    } else if max == g {
-        60.0 * (((b - r) / delta) + 2.0)
-    } else {
-        60.0 * (((r - g) / delta) + 4.0)
-    };
+/// Formats an RGB triple as an uppercase `#RRGGBB` hex string.
+fn to_hex(color: [u8; 3]) -> String {
+    let mut hex_color = String::new();
+    write!(
+        &mut hex_color,
+        "#{:02X}{:02X}{:02X}",
+        color[0], color[1], color[2]
+    )
+    .unwrap();
+    hex_color
+}
 
-    let saturation = if max == 0.0 { 0.0 } else { delta / max };
+/// Composites an image onto a white background, dropping any alpha channel.
+fn composite_onto_white(img: &DynamicImage) -> DynamicImage {
+    let rgba = img.to_rgba8();
+    let mut rgb = image::RgbImage::new(rgba.width(), rgba.height());
+
+    for (x, y, pixel) in rgba.enumerate_pixels() {
+        let alpha = pixel[3] as f32 / 255.0;
+        let blend = |channel: u8| -> u8 {
+            (channel as f32 * alpha + 255.0 * (1.0 - alpha)).round() as u8
+        };
+        rgb.put_pixel(
+            x,
+            y,
+            Rgb([blend(pixel[0]), blend(pixel[1]), blend(pixel[2])]),
+        );
+    }
 
-    (hue, saturation, max)
+    DynamicImage::ImageRgb8(rgb)
 }
 
-// Function to calculate difference in hue, saturation and brightness
-fn hsb_diff(hsb1: (f32, f32, f32), hsb2: (f32, f32, f32)) -> (f32, f32, f32) {
-    let hue_diff = (hsb1.0 - hsb2.0).abs();
-    let saturation_diff = (hsb1.1 - hsb2.1).abs();
-    let brightness_diff = (hsb1.2 - hsb2.2).abs();
-    (saturation_diff, brightness_diff, hue_diff)
+/// A minimal NeuQuant-style self-organizing map used to quantize an image
+/// down to a small palette of representative colors.
+struct NeuQuant {
+    neurons: Vec<[f32; 3]>,
 }
 
-fn get_unique_colors(image_data: &[u8]) -> Vec<String> {
-    let img: DynamicImage = image::load_from_memory(image_data).expect("Failed to load image");
-    let mut color_count: HashMap<[u8; 3], u32> = HashMap::new();
+impl NeuQuant {
+    /// Creates `palette_size` neurons spread evenly along the gray diagonal.
+    fn new(palette_size: usize) -> Self {
+        let n = palette_size.max(1);
+        let neurons = (0..n)
+            .map(|i| {
+                let t = i as f32 / n as f32 * 255.0;
+                [t, t, t]
+            })
+            .collect();
+        NeuQuant { neurons }
+    }
 
-    for (_, _, pixel) in img.pixels() {
-        let rgb = pixel.to_rgb().0;
-        *color_count.entry(rgb).or_insert(0) += 1;
+    /// Finds the neuron closest to `sample` by squared RGB distance.
+    fn nearest(&self, sample: [f32; 3]) -> usize {
+        self.neurons
+            .iter()
+            .enumerate()
+            .map(|(i, n)| {
+                let dx = n[0] - sample[0];
+                let dy = n[1] - sample[1];
+                let dz = n[2] - sample[2];
+                (i, dx * dx + dy * dy + dz * dz)
+            })
+            .fold((0, f32::MAX), |best, cur| if cur.1 < best.1 { cur } else { best })
+            .0
     }
 
-    let all_colors = color_count.keys().collect::<Vec<_>>();
+    /// Runs `cycles` learning passes over `samples`, pulling the winning
+    /// neuron and its neighbors within a shrinking radius toward each
+    /// sampled color, with both radius and learning rate decaying
+    /// geometrically each cycle.
+    fn train(&mut self, samples: &[[f32; 3]], cycles: usize) {
+        let n = self.neurons.len();
+        if n == 0 || samples.is_empty() {
+            return;
+        }
 
-    let mut unique_colors = Vec::new();
+        let mut radius = (n as f32 / 2.0).max(1.0);
+        let mut learning_rate = 0.5_f32;
+
+        for _ in 0..cycles {
+            for &sample in samples {
+                let winner = self.nearest(sample) as isize;
+                let r = radius.round() as isize;
+
+                for offset in -r..=r {
+                    let idx = winner + offset;
+                    if idx < 0 || idx >= n as isize {
+                        continue;
+                    }
+                    let idx = idx as usize;
+                    let falloff = 1.0 - (offset.unsigned_abs() as f32 / (radius + 1.0));
+                    let lr = learning_rate * falloff.max(0.0);
+
+                    for c in 0..3 {
+                        let delta = (sample[c] - self.neurons[idx][c]) * lr;
+                        self.neurons[idx][c] = (self.neurons[idx][c] + delta).clamp(0.0, 255.0);
+                    }
+                }
+            }
 
-    for &color in all_colors {
-        let color_hsb: (f32, f32, f32) = rgb_to_hsb(Rgb(color)); // Corrected this line
-        if unique_colors.iter().all(|&unique| {
-            let (sat_diff, bri_diff, hue_diff) = hsb_diff(color_hsb, rgb_to_hsb(Rgb(unique)));
-            sat_diff > 0.1 && bri_diff > 0.1 && hue_diff > 10.0 // Adjust thresholds as needed
-        }) {
-            unique_colors.push(color);
-            if unique_colors.len() >= 20 {
-                break;
-            } // Limit to 5 unique colors
+            radius = (radius * 0.7).max(1.0);
+            learning_rate *= 0.8;
         }
     }
 
-    let mut results = Vec::new();
+    /// Reads out the current neuron colors, rounded to whole RGB bytes.
+    fn colors(&self) -> Vec<[u8; 3]> {
+        self.neurons
+            .iter()
+            .map(|n| [n[0].round() as u8, n[1].round() as u8, n[2].round() as u8])
+            .collect()
+    }
+}
+
+/// Extracts a palette of up to `palette_size` colors from the image, ordered
+/// from most to least populous. Uses the NeuQuant algorithm, subsampling
+/// pixels for speed on large images, and composites away any alpha channel
+/// onto white before sampling. An image with no more distinct colors than
+/// `palette_size` yields just those colors.
+fn quantize_palette(image_data: &[u8], palette_size: usize) -> Vec<String> {
+    let img: DynamicImage = image::load_from_memory(image_data).expect("Failed to load image");
+    let rgb_img = composite_onto_white(&img);
+
+    let mut color_count: HashMap<[u8; 3], u32> = HashMap::new();
+    for (_, _, pixel) in rgb_img.pixels() {
+        *color_count.entry(pixel.to_rgb().0).or_insert(0) += 1;
+    }
+
+    if palette_size == 0 {
+        return Vec::new();
+    }
+
+    // Fewer distinct colors than requested: return them directly rather
+    // than running the quantizer, which guarantees termination on small
+    // or flat images.
+    if color_count.len() <= palette_size {
+        let mut colors: Vec<([u8; 3], u32)> = color_count.into_iter().collect();
+        colors.sort_by(|a, b| b.1.cmp(&a.1));
+        return colors.into_iter().map(|(color, _)| to_hex(color)).collect();
+    }
 
-    // Convert channel data to hex
-    for color in unique_colors {
-        let mut hex_color = String::new();
-        write!(
-            &mut hex_color,
-            "#{:02X}{:02X}{:02X}",
-            color[0], color[1], color[2]
-        )
-        .unwrap();
-        results.push(hex_color);
+    let (width, height) = rgb_img.dimensions();
+    let pixel_count = (width as u64) * (height as u64);
+    // Subsample large images so training stays fast; always keep at least
+    // every pixel on small ones.
+    let stride = ((pixel_count as f64 / 20_000.0).sqrt().round() as u32).max(1);
+
+    let samples: Vec<[f32; 3]> = rgb_img
+        .enumerate_pixels()
+        .filter(|(x, y, _)| x % stride == 0 && y % stride == 0)
+        .map(|(_, _, pixel)| [pixel[0] as f32, pixel[1] as f32, pixel[2] as f32])
+        .collect();
+
+    let cycles = (samples.len() / 100).clamp(4, 32);
+    let mut net = NeuQuant::new(palette_size);
+    net.train(&samples, cycles);
+
+    let mut population = vec![0u32; palette_size];
+    for &sample in &samples {
+        population[net.nearest(sample)] += 1;
     }
 
-    results
+    let colors = net.colors();
+    let mut ranked: Vec<(usize, u32)> = population.into_iter().enumerate().collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1));
+
+    ranked
+        .into_iter()
+        .map(|(i, _)| to_hex(colors[i]))
+        .collect()
 }
 
 fn parse_exif_data(image_data: &[u8]) -> Vec<[String; 2]> {
@@ -170,20 +287,144 @@ fn parse_exif_data(image_data: &[u8]) -> Vec<[String; 2]> {
     exif_tags
 }
 
+/// Converts an EXIF `"YYYY:MM:DD HH:MM:SS"` timestamp into an ISO-8601 string.
+fn parse_exif_datetime(raw: &str) -> Option<String> {
+    let mut parts = raw.splitn(2, ' ');
+    let date_part = parts.next()?;
+    let time_part = parts.next().unwrap_or("00:00:00");
+
+    let date_fields: Vec<&str> = date_part.splitn(3, ':').collect();
+    if date_fields.len() != 3 {
+        return None;
+    }
+
+    Some(format!(
+        "{}-{}-{}T{}",
+        date_fields[0], date_fields[1], date_fields[2], time_part
+    ))
+}
+
+/// Reads a field's raw ASCII string value, e.g. for `Make`, `Model`, or
+/// `GPSLatitudeRef`. Reads the `Value::Ascii` bytes directly rather than
+/// going through `display_value()`, whose formatting (e.g. surrounding
+/// quotes) is meant for human-readable output, not for byte-exact
+/// comparisons like `lat_ref == "S"`.
+fn field_ascii(field: &exif::Field) -> Option<String> {
+    match &field.value {
+        exif::Value::Ascii(strings) => strings.first().map(|bytes| {
+            String::from_utf8_lossy(bytes)
+                .trim_end_matches('\0')
+                .trim()
+                .to_string()
+        }),
+        _ => None,
+    }
+}
+
+/// Reads the first rational (or signed rational) component of a field's
+/// value as an `f64`, e.g. for `ExposureTime` or `FNumber`.
+fn field_first_rational(field: &exif::Field) -> Option<f64> {
+    match &field.value {
+        exif::Value::Rational(values) => values.first().map(|r| r.to_f64()),
+        exif::Value::SRational(values) => values.first().map(|r| r.to_f64()),
+        _ => None,
+    }
+}
+
+/// Converts a `GPSLatitude`/`GPSLongitude` field (degrees, minutes, seconds
+/// as EXIF rationals) into unsigned decimal degrees.
+fn gps_dms_to_decimal(field: &exif::Field) -> Option<f64> {
+    if let exif::Value::Rational(values) = &field.value {
+        if let [deg, min, sec] = values.as_slice() {
+            return Some(deg.to_f64() + min.to_f64() / 60.0 + sec.to_f64() / 3600.0);
+        }
+    }
+    None
+}
+
+/// Parses the commonly-needed EXIF fields (capture date, camera, exposure
+/// settings, GPS coordinates) into typed values.
+fn parse_structured_metadata(image_data: &[u8]) -> ImageMetadata {
+    let cursor: Cursor<&[u8]> = Cursor::new(image_data);
+    let exif = match Reader::new().read_from_container(&mut cursor.clone()) {
+        Ok(exif) => exif,
+        Err(_) => return ImageMetadata::default(),
+    };
+
+    let capture_date = exif
+        .get_field(Tag::DateTimeOriginal, In::PRIMARY)
+        .or_else(|| exif.get_field(Tag::DateTime, In::PRIMARY))
+        .and_then(field_ascii)
+        .and_then(|raw| parse_exif_datetime(&raw));
+
+    let camera_make = exif.get_field(Tag::Make, In::PRIMARY).and_then(field_ascii);
+    let camera_model = exif.get_field(Tag::Model, In::PRIMARY).and_then(field_ascii);
+
+    let exposure_time = exif
+        .get_field(Tag::ExposureTime, In::PRIMARY)
+        .and_then(field_first_rational);
+    let iso = exif
+        .get_field(Tag::PhotographicSensitivity, In::PRIMARY)
+        .and_then(|field| field.value.get_uint(0))
+        .map(|value| value as f64);
+    let f_number = exif
+        .get_field(Tag::FNumber, In::PRIMARY)
+        .and_then(field_first_rational);
+
+    let lat_ref = exif.get_field(Tag::GPSLatitudeRef, In::PRIMARY).and_then(field_ascii);
+    let lon_ref = exif.get_field(Tag::GPSLongitudeRef, In::PRIMARY).and_then(field_ascii);
+
+    let gps_latitude = exif
+        .get_field(Tag::GPSLatitude, In::PRIMARY)
+        .and_then(gps_dms_to_decimal)
+        .map(|degrees| if lat_ref.as_deref() == Some("S") { -degrees } else { degrees });
+    let gps_longitude = exif
+        .get_field(Tag::GPSLongitude, In::PRIMARY)
+        .and_then(gps_dms_to_decimal)
+        .map(|degrees| if lon_ref.as_deref() == Some("W") { -degrees } else { degrees });
+
+    ImageMetadata {
+        capture_date,
+        camera_make,
+        camera_model,
+        exposure_time,
+        iso,
+        f_number,
+        gps_latitude,
+        gps_longitude,
+    }
+}
+
 #[wasm_bindgen]
 pub fn analyze_image(image_data: &[u8]) -> JsValue {
     let exif_data: Vec<[String; 2]> = parse_exif_data(image_data);
-    let unique_colors: Vec<String> = get_unique_colors(image_data);
+    let unique_colors: Vec<String> = quantize_palette(image_data, DEFAULT_PALETTE_SIZE);
+    let metadata: ImageMetadata = parse_structured_metadata(image_data);
 
     let analysis: ImageAnalysis = ImageAnalysis {
         exif_data,
         unique_colors,
+        metadata,
     };
 
     // Convert the combined data into a JsValue
     to_value(&analysis).unwrap_or(JsValue::UNDEFINED)
 }
 
+/// Extracts a dominant-color palette of up to `palette_size` hex colors from
+/// the image, ordered from most to least populous, using NeuQuant quantization.
+#[wasm_bindgen]
+pub fn extract_palette(image_data: &[u8], palette_size: usize) -> Vec<String> {
+    quantize_palette(image_data, palette_size)
+}
+
+/// Resizes an image, applying its EXIF orientation first. `metadata_mode`
+/// (`strip`, `preserve`, or `normalize`) controls what happens to the
+/// source's EXIF metadata in the output — see `metadata::MetadataMode`.
+/// ICC profiles are not carried over in any mode.
+/// `preserve`/`normalize` are currently only honored when the source is a
+/// JPEG with an APP1 EXIF segment and `format` is `"jpeg"`; any other
+/// combination silently falls back to `strip` behavior.
 #[wasm_bindgen]
 pub fn resize_image(
     image_data: &[u8],
@@ -191,6 +432,7 @@ pub fn resize_image(
     height: u32,
     format: &str,
     filter: &str,
+    metadata_mode: &str,
 ) -> Vec<u8> {
     let img: DynamicImage = image::load_from_memory(image_data).unwrap();
 
@@ -213,7 +455,17 @@ pub fn resize_image(
 
     let resized: DynamicImage = img.resize_to_fill(width, height, filter_type);
 
-    let image_format: ImageFormat = match format {
+    let encoded: Vec<u8> = encode_image(&resized, format);
+
+    // Orientation has already been baked into `resized` above, so a
+    // preserved or normalized EXIF block must not carry a stale
+    // Orientation tag forward, or viewers would rotate the image twice.
+    metadata::apply(metadata::MetadataMode::parse(metadata_mode), image_data, encoded)
+}
+
+/// Maps a format name to the corresponding `image` crate format, defaulting to PNG.
+fn parse_image_format(format: &str) -> ImageFormat {
+    match format {
         "png" => ImageFormat::Png,
         "webp" => ImageFormat::WebP,
         "jpeg" => ImageFormat::Jpeg,
@@ -223,13 +475,423 @@ pub fn resize_image(
         "tiff" => ImageFormat::Tiff,
         "ico" => ImageFormat::Ico,
         _ => ImageFormat::Png, // Default format
-    };
+    }
+}
 
+/// Encodes an image to bytes in the given format.
+fn encode_image(img: &DynamicImage, format: &str) -> Vec<u8> {
     let mut result: Vec<u8> = Vec::new();
     {
         let mut cursor: Cursor<&mut Vec<u8>> = Cursor::new(&mut result);
-        resized.write_to(&mut cursor, image_format).unwrap();
+        img.write_to(&mut cursor, parse_image_format(format)).unwrap();
     }
-
     result
 }
+
+/// Applies an arbitrary rotation (0/90/180/270 degrees clockwise) and
+/// independent horizontal/vertical mirroring to an image, without resizing.
+/// Unlike `resize_image`, this does not read or apply EXIF orientation —
+/// see `normalize_orientation` for that.
+#[wasm_bindgen]
+pub fn transform_image(
+    image_data: &[u8],
+    rotation: u16,
+    flip_h: bool,
+    flip_v: bool,
+    format: &str,
+) -> Vec<u8> {
+    let img: DynamicImage = image::load_from_memory(image_data).unwrap();
+
+    let mut img: DynamicImage = match rotation {
+        90 => img.rotate90(),
+        180 => img.rotate180(),
+        270 => img.rotate270(),
+        _ => img, // 0 or any other value: no rotation
+    };
+
+    if flip_h {
+        img = img.fliph();
+    }
+    if flip_v {
+        img = img.flipv();
+    }
+
+    // Ensure the image is in a color space compatible with the target format.
+    if format == "jpeg" {
+        img = DynamicImage::ImageRgb8(img.to_rgb8());
+    }
+
+    encode_image(&img, format)
+}
+
+/// Bakes the image's EXIF orientation into its pixel data and re-encodes it,
+/// so viewers that don't honor the orientation tag still display it upright.
+#[wasm_bindgen]
+pub fn normalize_orientation(image_data: &[u8], format: &str) -> Vec<u8> {
+    let img: DynamicImage = image::load_from_memory(image_data).unwrap();
+
+    let orientation: u16 = read_orientation(image_data);
+    let mut img: DynamicImage = apply_orientation(img, orientation);
+
+    // Ensure the image is in a color space compatible with the target format.
+    if format == "jpeg" {
+        img = DynamicImage::ImageRgb8(img.to_rgb8());
+    }
+
+    encode_image(&img, format)
+}
+
+/// Converts an image to the named colorspace (`grayscale`, `hsv`, `from_hsv`,
+/// or `rgb`) and re-encodes it. For `hsv`, hue/saturation/value are packed
+/// into the R/G/B channels of the output image, since image formats have no
+/// native HSV representation; `from_hsv` reverses that packing back to plain
+/// RGB.
+#[wasm_bindgen]
+pub fn convert_colorspace(image_data: &[u8], target: &str, format: &str) -> Vec<u8> {
+    let img: DynamicImage = image::load_from_memory(image_data).unwrap();
+    let converted: DynamicImage = colorspace::convert(&img, target);
+    encode_image(&converted, format)
+}
+
+/// Extracts a single channel (`r`, `g`, `b`, `h`, `s`, `v`, or `luma`) from an
+/// image as a grayscale PNG, for building histograms, thresholding, or
+/// false-color views client-side.
+#[wasm_bindgen]
+pub fn channel(image_data: &[u8], which: &str) -> Vec<u8> {
+    let img: DynamicImage = image::load_from_memory(image_data).unwrap();
+    let extracted: DynamicImage = colorspace::extract_channel(&img, which);
+    encode_image(&extracted, "png")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Encodes a small in-memory RGB image to PNG bytes for feeding into
+    /// functions that expect raw image bytes.
+    fn encode_test_png(pixels: &[[u8; 3]], width: u32, height: u32) -> Vec<u8> {
+        let mut img = image::RgbImage::new(width, height);
+        for (i, &[r, g, b]) in pixels.iter().enumerate() {
+            let x = (i as u32) % width;
+            let y = (i as u32) / width;
+            img.put_pixel(x, y, Rgb([r, g, b]));
+        }
+
+        let mut buf = Vec::new();
+        DynamicImage::ImageRgb8(img)
+            .write_to(&mut Cursor::new(&mut buf), ImageFormat::Png)
+            .unwrap();
+        buf
+    }
+
+    #[test]
+    fn quantize_palette_returns_all_colors_when_fewer_than_requested() {
+        let pixels = [[255, 0, 0], [255, 0, 0], [0, 255, 0], [0, 255, 0]];
+        let png = encode_test_png(&pixels, 2, 2);
+
+        // Only 2 distinct colors present, well under the requested 5: the
+        // NeuQuant quantizer should be skipped entirely and every distinct
+        // color returned directly.
+        let palette = quantize_palette(&png, 5);
+
+        assert_eq!(palette.len(), 2);
+        assert!(palette.contains(&"#FF0000".to_string()));
+        assert!(palette.contains(&"#00FF00".to_string()));
+    }
+
+    #[test]
+    fn quantize_palette_returns_empty_for_zero_palette_size() {
+        let pixels = [[255, 0, 0], [0, 255, 0], [0, 0, 255], [255, 255, 255]];
+        let png = encode_test_png(&pixels, 2, 2);
+
+        assert_eq!(quantize_palette(&png, 0), Vec::<String>::new());
+    }
+
+    #[test]
+    fn quantize_palette_ranks_by_population() {
+        let pixels = [
+            [255, 0, 0],
+            [255, 0, 0],
+            [255, 0, 0],
+            [0, 0, 255],
+        ];
+        let png = encode_test_png(&pixels, 2, 2);
+
+        let palette = quantize_palette(&png, 5);
+
+        assert_eq!(palette[0], "#FF0000");
+    }
+
+    #[test]
+    fn parse_exif_datetime_converts_to_iso8601() {
+        assert_eq!(
+            parse_exif_datetime("2024:03:15 10:30:00"),
+            Some("2024-03-15T10:30:00".to_string())
+        );
+    }
+
+    #[test]
+    fn parse_exif_datetime_rejects_malformed_input() {
+        assert_eq!(parse_exif_datetime("not a date"), None);
+    }
+
+    #[test]
+    fn gps_dms_to_decimal_converts_degrees_minutes_seconds() {
+        let field = exif::Field {
+            tag: Tag::GPSLatitude,
+            ifd_num: In::PRIMARY,
+            value: exif::Value::Rational(vec![
+                exif::Rational { num: 40, denom: 1 },
+                exif::Rational { num: 30, denom: 1 },
+                exif::Rational { num: 0, denom: 1 },
+            ]),
+        };
+
+        let decimal = gps_dms_to_decimal(&field).expect("3-component rational GPS field");
+
+        assert!((decimal - 40.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn gps_dms_to_decimal_rejects_wrong_component_count() {
+        let field = exif::Field {
+            tag: Tag::GPSLatitude,
+            ifd_num: In::PRIMARY,
+            value: exif::Value::Rational(vec![exif::Rational { num: 40, denom: 1 }]),
+        };
+
+        assert_eq!(gps_dms_to_decimal(&field), None);
+    }
+
+    /// Builds a minimal little-endian TIFF/EXIF blob with a `DateTime` tag
+    /// in IFD0 and a GPS sub-IFD (`GPSLatitudeRef` + `GPSLatitude`), so
+    /// `parse_structured_metadata` can be exercised against the real
+    /// `exif::Reader` instead of only its own hand-written helpers.
+    fn build_tiff_with_gps_and_datetime(lat_ref: &str, datetime: &str) -> Vec<u8> {
+        const TIFF_HEADER_LEN: usize = 8;
+        const IFD_ENTRY_LEN: usize = 12;
+        const DATE_TIME_TAG: u16 = 0x0132;
+        const GPS_INFO_IFD_POINTER_TAG: u16 = 0x8825;
+        const GPS_LATITUDE_REF_TAG: u16 = 0x0001;
+        const GPS_LATITUDE_TAG: u16 = 0x0002;
+
+        let ifd0_len = 2 + 2 * IFD_ENTRY_LEN + 4;
+        let datetime_offset = TIFF_HEADER_LEN + ifd0_len;
+
+        let datetime_bytes: Vec<u8> = datetime.bytes().chain(std::iter::once(0)).collect();
+        let gps_ifd_offset = datetime_offset + datetime_bytes.len();
+
+        let gps_ifd_len = 2 + 2 * IFD_ENTRY_LEN + 4;
+        let gps_lat_rationals_offset = gps_ifd_offset + gps_ifd_len;
+
+        let mut tiff = Vec::new();
+
+        // TIFF header
+        tiff.extend_from_slice(b"II");
+        tiff.extend_from_slice(&42u16.to_le_bytes());
+        tiff.extend_from_slice(&(TIFF_HEADER_LEN as u32).to_le_bytes());
+
+        // IFD0: DateTime + GPSInfoIFDPointer
+        tiff.extend_from_slice(&2u16.to_le_bytes());
+        tiff.extend_from_slice(&DATE_TIME_TAG.to_le_bytes());
+        tiff.extend_from_slice(&2u16.to_le_bytes()); // type: ASCII
+        tiff.extend_from_slice(&(datetime_bytes.len() as u32).to_le_bytes());
+        tiff.extend_from_slice(&(datetime_offset as u32).to_le_bytes());
+        tiff.extend_from_slice(&GPS_INFO_IFD_POINTER_TAG.to_le_bytes());
+        tiff.extend_from_slice(&4u16.to_le_bytes()); // type: LONG
+        tiff.extend_from_slice(&1u32.to_le_bytes());
+        tiff.extend_from_slice(&(gps_ifd_offset as u32).to_le_bytes());
+        tiff.extend_from_slice(&0u32.to_le_bytes()); // next IFD offset
+        assert_eq!(tiff.len(), datetime_offset);
+
+        // DateTime string value
+        tiff.extend_from_slice(&datetime_bytes);
+        assert_eq!(tiff.len(), gps_ifd_offset);
+
+        // GPS IFD: GPSLatitudeRef (inline) + GPSLatitude (out of line)
+        tiff.extend_from_slice(&2u16.to_le_bytes());
+        tiff.extend_from_slice(&GPS_LATITUDE_REF_TAG.to_le_bytes());
+        tiff.extend_from_slice(&2u16.to_le_bytes()); // type: ASCII
+        tiff.extend_from_slice(&2u32.to_le_bytes());
+        let mut ref_value = [0u8; 4];
+        ref_value[0] = lat_ref.as_bytes()[0];
+        tiff.extend_from_slice(&ref_value);
+        tiff.extend_from_slice(&GPS_LATITUDE_TAG.to_le_bytes());
+        tiff.extend_from_slice(&5u16.to_le_bytes()); // type: RATIONAL
+        tiff.extend_from_slice(&3u32.to_le_bytes());
+        tiff.extend_from_slice(&(gps_lat_rationals_offset as u32).to_le_bytes());
+        tiff.extend_from_slice(&0u32.to_le_bytes()); // next IFD offset
+        assert_eq!(tiff.len(), gps_lat_rationals_offset);
+
+        // GPSLatitude rationals: 40 deg, 30 min, 0 sec => 40.5 degrees
+        for &(num, denom) in &[(40u32, 1u32), (30, 1), (0, 1)] {
+            tiff.extend_from_slice(&num.to_le_bytes());
+            tiff.extend_from_slice(&denom.to_le_bytes());
+        }
+
+        tiff
+    }
+
+    /// Wraps a TIFF body in a minimal JPEG with an APP1 EXIF segment, the
+    /// same way `metadata.rs`'s tests do.
+    fn build_jpeg_with_exif(tiff: &[u8]) -> Vec<u8> {
+        let mut jpeg = vec![0xFF, 0xD8];
+        let exif_header = b"Exif\0\0";
+        let segment_len = 2 + exif_header.len() + tiff.len();
+        jpeg.extend_from_slice(&[0xFF, 0xE1]);
+        jpeg.extend_from_slice(&(segment_len as u16).to_be_bytes());
+        jpeg.extend_from_slice(exif_header);
+        jpeg.extend_from_slice(tiff);
+        jpeg.extend_from_slice(&[0xFF, 0xD9]);
+        jpeg
+    }
+
+    #[test]
+    fn parse_structured_metadata_flips_gps_sign_and_parses_capture_date_from_real_exif() {
+        let tiff = build_tiff_with_gps_and_datetime("S", "2024:03:15 10:30:00");
+        let jpeg = build_jpeg_with_exif(&tiff);
+
+        let metadata = parse_structured_metadata(&jpeg);
+
+        assert_eq!(metadata.capture_date.as_deref(), Some("2024-03-15T10:30:00"));
+
+        let latitude = metadata.gps_latitude.expect("gps_latitude should be present");
+        assert!(latitude < 0.0, "southern hemisphere latitude should be negative, got {latitude}");
+        assert!((latitude + 40.5).abs() < 1e-6);
+    }
+
+    /// Builds a minimal little-endian TIFF/EXIF blob containing only an
+    /// `Orientation` entry in IFD0.
+    fn build_orientation_tiff(orientation: u16) -> Vec<u8> {
+        let mut tiff = Vec::new();
+        tiff.extend_from_slice(b"II");
+        tiff.extend_from_slice(&42u16.to_le_bytes());
+        tiff.extend_from_slice(&8u32.to_le_bytes());
+        tiff.extend_from_slice(&1u16.to_le_bytes()); // entry count
+        tiff.extend_from_slice(&0x0112u16.to_le_bytes()); // Orientation tag
+        tiff.extend_from_slice(&3u16.to_le_bytes()); // type: SHORT
+        tiff.extend_from_slice(&1u32.to_le_bytes());
+        tiff.extend_from_slice(&orientation.to_le_bytes());
+        tiff.extend_from_slice(&[0, 0]); // pad to 4-byte value field
+        tiff.extend_from_slice(&0u32.to_le_bytes()); // next IFD offset
+        tiff
+    }
+
+    /// Splices a TIFF body in as a JPEG APP1 EXIF segment right after the
+    /// SOI marker of an already-encoded JPEG.
+    fn splice_app1_exif(jpeg: &[u8], tiff: &[u8]) -> Vec<u8> {
+        let exif_header = b"Exif\0\0";
+        let segment_len = 2 + exif_header.len() + tiff.len();
+        let mut out = Vec::with_capacity(jpeg.len() + 2 + segment_len);
+        out.extend_from_slice(&jpeg[0..2]);
+        out.extend_from_slice(&[0xFF, 0xE1]);
+        out.extend_from_slice(&(segment_len as u16).to_be_bytes());
+        out.extend_from_slice(exif_header);
+        out.extend_from_slice(tiff);
+        out.extend_from_slice(&jpeg[2..]);
+        out
+    }
+
+    fn encode_test_jpeg(pixels: &[[u8; 3]], width: u32, height: u32) -> Vec<u8> {
+        let mut img = image::RgbImage::new(width, height);
+        for (i, &[r, g, b]) in pixels.iter().enumerate() {
+            let x = (i as u32) % width;
+            let y = (i as u32) / width;
+            img.put_pixel(x, y, Rgb([r, g, b]));
+        }
+
+        let mut buf = Vec::new();
+        DynamicImage::ImageRgb8(img)
+            .write_to(&mut Cursor::new(&mut buf), ImageFormat::Jpeg)
+            .unwrap();
+        buf
+    }
+
+    #[test]
+    fn transform_image_rotate90_matches_dynamicimage_rotate90() {
+        let pixels = [[255, 0, 0], [0, 255, 0], [0, 0, 255], [255, 255, 0]];
+        let png = encode_test_png(&pixels, 2, 2);
+
+        let transformed = transform_image(&png, 90, false, false, "png");
+        let transformed_img = image::load_from_memory(&transformed).unwrap().to_rgb8();
+
+        let original_img = image::load_from_memory(&png).unwrap();
+        let expected = original_img.rotate90().to_rgb8();
+
+        assert_eq!(transformed_img, expected);
+    }
+
+    #[test]
+    fn transform_image_flip_h_mirrors_pixels() {
+        let pixels = [[255, 0, 0], [0, 255, 0], [0, 0, 255], [255, 255, 0]];
+        let png = encode_test_png(&pixels, 2, 2);
+
+        let transformed = transform_image(&png, 0, true, false, "png");
+        let transformed_img = image::load_from_memory(&transformed).unwrap().to_rgb8();
+
+        let original_img = image::load_from_memory(&png).unwrap();
+        let expected = original_img.fliph().to_rgb8();
+
+        assert_eq!(transformed_img, expected);
+    }
+
+    #[test]
+    fn normalize_orientation_bakes_in_exif_rotation() {
+        let pixels = [[255, 0, 0], [0, 255, 0], [0, 0, 255], [255, 255, 0]];
+        let jpeg = encode_test_jpeg(&pixels, 2, 2);
+        let oriented_jpeg = splice_app1_exif(&jpeg, &build_orientation_tiff(6));
+
+        let normalized = normalize_orientation(&oriented_jpeg, "png");
+        let normalized_img = image::load_from_memory(&normalized).unwrap().to_rgb8();
+
+        // normalize_orientation decodes ignoring the embedded orientation
+        // (as `image` always does) and then applies it explicitly, so the
+        // expectation is built the same way from the same source bytes.
+        let decoded_without_orientation = image::load_from_memory(&oriented_jpeg).unwrap();
+        let expected = apply_orientation(decoded_without_orientation, 6).to_rgb8();
+
+        assert_eq!(normalized_img, expected);
+    }
+
+    #[test]
+    fn convert_colorspace_grayscale_matches_to_luma8() {
+        let pixels = [[255, 0, 0], [0, 255, 0], [0, 0, 255], [255, 255, 255]];
+        let png = encode_test_png(&pixels, 2, 2);
+
+        let gray = convert_colorspace(&png, "grayscale", "png");
+        let gray_img = image::load_from_memory(&gray).unwrap().to_luma8();
+
+        let original = image::load_from_memory(&png).unwrap();
+        assert_eq!(gray_img, original.to_luma8());
+    }
+
+    #[test]
+    fn convert_colorspace_hsv_then_from_hsv_round_trips() {
+        let pixels = [[255, 0, 128]];
+        let png = encode_test_png(&pixels, 1, 1);
+
+        let hsv = convert_colorspace(&png, "hsv", "png");
+        let back = convert_colorspace(&hsv, "from_hsv", "png");
+        let back_rgb = image::load_from_memory(&back).unwrap().to_rgb8();
+
+        let pixel = back_rgb.get_pixel(0, 0);
+        for (c, &expected) in [255u8, 0, 128].iter().enumerate() {
+            assert!(
+                (pixel[c] as i16 - expected as i16).abs() <= 2,
+                "channel {c}: got {:?}",
+                pixel
+            );
+        }
+    }
+
+    #[test]
+    fn channel_extracts_red_channel_as_grayscale() {
+        let pixels = [[10, 20, 30]];
+        let png = encode_test_png(&pixels, 1, 1);
+
+        let red = channel(&png, "r");
+        let red_img = image::load_from_memory(&red).unwrap().to_luma8();
+
+        assert_eq!(red_img.get_pixel(0, 0)[0], 10);
+    }
+}
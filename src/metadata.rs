@@ -0,0 +1,298 @@
+//! Raw-byte handling of the JPEG APP1 EXIF segment for `resize_image`'s
+//! `metadata_mode`. Operates on the encoder's output bytes directly rather
+//! than through the `exif` crate (which this codebase only uses for
+//! reading), since neither `exif` nor `image` support writing EXIF back out.
+
+const EXIF_HEADER: &[u8] = b"Exif\0\0";
+const ORIENTATION_TAG: u16 = 0x0112;
+
+/// How `resize_image` should handle the source image's EXIF metadata in its
+/// output. ICC profiles are dropped in every mode.
+pub enum MetadataMode {
+    /// Drop all metadata. The privacy-friendly default for the web.
+    Strip,
+    /// Copy the original EXIF block through, but rewrite `Orientation` to 1
+    /// so viewers don't re-apply a rotation that's already been baked in.
+    Preserve,
+    /// Copy the original EXIF block through, but drop the `Orientation` tag
+    /// entirely rather than just neutralizing it.
+    Normalize,
+}
+
+impl MetadataMode {
+    pub fn parse(mode: &str) -> Self {
+        match mode {
+            "preserve" => MetadataMode::Preserve,
+            "normalize" => MetadataMode::Normalize,
+            _ => MetadataMode::Strip, // "strip" and anything unrecognized
+        }
+    }
+}
+
+/// Applies `mode` to a freshly-encoded image, carrying metadata over from
+/// `original_data` (the source bytes passed in before decoding) as needed.
+/// Only JPEG output with a JPEG-style APP1 EXIF segment in the source is
+/// currently supported; any other combination leaves `encoded` untouched.
+pub fn apply(mode: MetadataMode, original_data: &[u8], encoded: Vec<u8>) -> Vec<u8> {
+    let clear_orientation = match mode {
+        MetadataMode::Strip => return encoded,
+        MetadataMode::Preserve => false,
+        MetadataMode::Normalize => true,
+    };
+
+    let Some((tiff_start, tiff_end)) = find_app1_exif(original_data) else {
+        return encoded;
+    };
+    if !encoded.starts_with(&[0xFF, 0xD8]) {
+        return encoded;
+    }
+
+    let mut tiff = original_data[tiff_start..tiff_end].to_vec();
+    patch_orientation(&mut tiff, clear_orientation);
+
+    match insert_app1_exif(encoded, &tiff) {
+        Ok(result) => result,
+        // The TIFF body is too large for APP1's 16-bit length field
+        // (cameras can embed thumbnails/MakerNotes that push it past
+        // 64KB) — fall back to stripping rather than emit a JPEG with a
+        // truncated, structurally invalid APP1 segment.
+        Err(encoded) => encoded,
+    }
+}
+
+/// Finds the JPEG APP1 EXIF segment in `jpeg`, returning the byte range of
+/// its TIFF body (after the `Exif\0\0` marker), if present.
+fn find_app1_exif(jpeg: &[u8]) -> Option<(usize, usize)> {
+    if jpeg.len() < 4 || jpeg[0..2] != [0xFF, 0xD8] {
+        return None;
+    }
+
+    let mut pos = 2;
+    while pos + 4 <= jpeg.len() {
+        if jpeg[pos] != 0xFF {
+            break;
+        }
+        let marker = jpeg[pos + 1];
+
+        // Markers with no payload: standalone or RST0-RST7.
+        if marker == 0x01 || (0xD0..=0xD7).contains(&marker) {
+            pos += 2;
+            continue;
+        }
+        if marker == 0xD9 {
+            break; // EOI
+        }
+
+        let len = u16::from_be_bytes([jpeg[pos + 2], jpeg[pos + 3]]) as usize;
+        let segment_start = pos + 4;
+        let segment_end = pos + 2 + len;
+        if segment_end > jpeg.len() || segment_start > segment_end {
+            break;
+        }
+
+        if marker == 0xE1 && jpeg[segment_start..segment_end].starts_with(EXIF_HEADER) {
+            return Some((segment_start + EXIF_HEADER.len(), segment_end));
+        }
+        if marker == 0xDA {
+            break; // start of scan: no more header segments follow
+        }
+
+        pos = segment_end;
+    }
+
+    None
+}
+
+/// Rewrites or drops the `Orientation` entry in a TIFF/EXIF IFD0 in place.
+fn patch_orientation(tiff: &mut [u8], clear: bool) {
+    if tiff.len() < 8 {
+        return;
+    }
+
+    let little_endian = match &tiff[0..2] {
+        b"II" => true,
+        b"MM" => false,
+        _ => return,
+    };
+    let read_u16 = |b: &[u8]| -> u16 {
+        if little_endian {
+            u16::from_le_bytes([b[0], b[1]])
+        } else {
+            u16::from_be_bytes([b[0], b[1]])
+        }
+    };
+    let read_u32 = |b: &[u8]| -> u32 {
+        if little_endian {
+            u32::from_le_bytes([b[0], b[1], b[2], b[3]])
+        } else {
+            u32::from_be_bytes([b[0], b[1], b[2], b[3]])
+        }
+    };
+
+    let ifd0_offset = read_u32(&tiff[4..8]) as usize;
+    if ifd0_offset + 2 > tiff.len() {
+        return;
+    }
+    let entry_count = read_u16(&tiff[ifd0_offset..ifd0_offset + 2]) as usize;
+
+    for i in 0..entry_count {
+        let entry_start = ifd0_offset + 2 + i * 12;
+        if entry_start + 12 > tiff.len() {
+            break;
+        }
+        let tag = read_u16(&tiff[entry_start..entry_start + 2]);
+        if tag != ORIENTATION_TAG {
+            continue;
+        }
+
+        if clear {
+            // Blank the tag id itself: 0x0000 is reserved and ignored by
+            // EXIF readers, which is the cheapest way to drop an entry
+            // without resizing and re-offsetting the whole IFD.
+            tiff[entry_start..entry_start + 2].copy_from_slice(&[0, 0]);
+        } else {
+            // Orientation is type SHORT, count 1: the value is stored
+            // left-justified in the first 2 bytes of the 4-byte value field.
+            let value_start = entry_start + 8;
+            let one: [u8; 2] = if little_endian {
+                1u16.to_le_bytes()
+            } else {
+                1u16.to_be_bytes()
+            };
+            tiff[value_start..value_start + 2].copy_from_slice(&one);
+        }
+        break; // Orientation appears at most once in IFD0.
+    }
+}
+
+/// Inserts a TIFF body as a new APP1 EXIF segment right after a JPEG's SOI
+/// marker. Fails (returning `jpeg` unchanged) if the segment, including its
+/// own 2-byte length field, would overflow that field's `u16` range.
+fn insert_app1_exif(jpeg: Vec<u8>, tiff: &[u8]) -> Result<Vec<u8>, Vec<u8>> {
+    let segment_len = 2 + EXIF_HEADER.len() + tiff.len();
+    if segment_len > u16::MAX as usize {
+        return Err(jpeg);
+    }
+
+    let mut result = Vec::with_capacity(jpeg.len() + 2 + segment_len);
+
+    result.extend_from_slice(&jpeg[0..2]); // SOI
+    result.extend_from_slice(&[0xFF, 0xE1]);
+    result.extend_from_slice(&(segment_len as u16).to_be_bytes());
+    result.extend_from_slice(EXIF_HEADER);
+    result.extend_from_slice(tiff);
+    result.extend_from_slice(&jpeg[2..]);
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a minimal TIFF/EXIF IFD0 body: "II" byte order, optionally
+    /// containing a single `Orientation` (SHORT) entry set to `6`.
+    fn build_tiff(with_orientation: bool) -> Vec<u8> {
+        let mut tiff = Vec::new();
+        tiff.extend_from_slice(b"II");
+        tiff.extend_from_slice(&42u16.to_le_bytes());
+        tiff.extend_from_slice(&8u32.to_le_bytes()); // IFD0 offset
+
+        let entry_count: u16 = if with_orientation { 1 } else { 0 };
+        tiff.extend_from_slice(&entry_count.to_le_bytes());
+
+        if with_orientation {
+            tiff.extend_from_slice(&ORIENTATION_TAG.to_le_bytes());
+            tiff.extend_from_slice(&3u16.to_le_bytes()); // type: SHORT
+            tiff.extend_from_slice(&1u32.to_le_bytes()); // count
+            tiff.extend_from_slice(&6u16.to_le_bytes()); // value: rotate 90 CW
+            tiff.extend_from_slice(&[0, 0]); // pad to 4-byte value field
+        }
+
+        tiff.extend_from_slice(&0u32.to_le_bytes()); // next IFD offset: none
+        tiff
+    }
+
+    /// Wraps a TIFF body in a minimal JPEG: SOI, APP1 EXIF segment, EOI.
+    fn build_jpeg_with_exif(tiff: &[u8]) -> Vec<u8> {
+        let mut jpeg = vec![0xFF, 0xD8];
+        let segment_len = 2 + EXIF_HEADER.len() + tiff.len();
+        jpeg.extend_from_slice(&[0xFF, 0xE1]);
+        jpeg.extend_from_slice(&(segment_len as u16).to_be_bytes());
+        jpeg.extend_from_slice(EXIF_HEADER);
+        jpeg.extend_from_slice(tiff);
+        jpeg.extend_from_slice(&[0xFF, 0xD9]);
+        jpeg
+    }
+
+    /// Reads back the `Orientation` entry's value from a TIFF body, or
+    /// `None` if no entry with that tag is present.
+    fn read_orientation_value(tiff: &[u8]) -> Option<u16> {
+        let ifd0_offset = u32::from_le_bytes(tiff[4..8].try_into().unwrap()) as usize;
+        let entry_count = u16::from_le_bytes(tiff[ifd0_offset..ifd0_offset + 2].try_into().unwrap());
+
+        for i in 0..entry_count as usize {
+            let entry_start = ifd0_offset + 2 + i * 12;
+            let tag = u16::from_le_bytes(tiff[entry_start..entry_start + 2].try_into().unwrap());
+            if tag == ORIENTATION_TAG {
+                let value_start = entry_start + 8;
+                return Some(u16::from_le_bytes(
+                    tiff[value_start..value_start + 2].try_into().unwrap(),
+                ));
+            }
+        }
+        None
+    }
+
+    #[test]
+    fn apply_strip_drops_exif_entirely() {
+        let source = build_jpeg_with_exif(&build_tiff(true));
+        let encoded = vec![0xFF, 0xD8, 0xFF, 0xD9];
+
+        let result = apply(MetadataMode::Strip, &source, encoded.clone());
+
+        assert_eq!(result, encoded);
+    }
+
+    #[test]
+    fn apply_preserve_neutralizes_orientation_without_dropping_the_tag() {
+        let source = build_jpeg_with_exif(&build_tiff(true));
+        let encoded = vec![0xFF, 0xD8, 0xFF, 0xD9];
+
+        let result = apply(MetadataMode::Preserve, &source, encoded);
+
+        let (tiff_start, tiff_end) = find_app1_exif(&result).expect("APP1 segment present");
+        assert_eq!(read_orientation_value(&result[tiff_start..tiff_end]), Some(1));
+    }
+
+    #[test]
+    fn apply_normalize_drops_the_orientation_entry() {
+        let source = build_jpeg_with_exif(&build_tiff(true));
+        let encoded = vec![0xFF, 0xD8, 0xFF, 0xD9];
+
+        let result = apply(MetadataMode::Normalize, &source, encoded);
+
+        let (tiff_start, tiff_end) = find_app1_exif(&result).expect("APP1 segment present");
+        assert_eq!(read_orientation_value(&result[tiff_start..tiff_end]), None);
+    }
+
+    #[test]
+    fn apply_preserve_is_a_no_op_without_a_source_app1_segment() {
+        let source = vec![0xFF, 0xD8, 0xFF, 0xD9]; // no EXIF at all
+        let encoded = vec![0xFF, 0xD8, 0xFF, 0xD9];
+
+        let result = apply(MetadataMode::Preserve, &source, encoded.clone());
+
+        assert_eq!(result, encoded);
+    }
+
+    #[test]
+    fn insert_app1_exif_rejects_oversized_tiff_body() {
+        let jpeg = vec![0xFF, 0xD8, 0xFF, 0xD9];
+        let oversized_tiff = vec![0u8; u16::MAX as usize];
+
+        let result = insert_app1_exif(jpeg.clone(), &oversized_tiff);
+
+        assert_eq!(result, Err(jpeg));
+    }
+}